@@ -1,7 +1,9 @@
 use anyhow::{Context, Result};
 use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
+use std::collections::HashMap;
 use std::fs;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::time::Instant;
@@ -15,10 +17,79 @@ use winapi::um::fileapi::{CreateDirectoryW};
 #[cfg(windows)]
 use std::ptr;
 
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+
+/// 条目的类型，决定 `move_single_item` 如何重建它
+enum EntryKind {
+    Dir,
+    File,
+    /// 符号链接，其指向的目标在移动时由 `fs::read_link` 读取
+    Symlink,
+    /// 与之前某个已处理文件共享同一 inode 的硬链接，携带该文件已移动到的目标路径
+    HardlinkOf(PathBuf),
+}
 
 struct FileMoveTask {
     src: PathBuf,
     dst: PathBuf,
+    /// 文件大小（字节），目录和符号链接固定为 0，用于按字节驱动进度条
+    size: u64,
+    kind: EntryKind,
+}
+
+/// 移动行为的配置选项，参考 fs_extra 的 `CopyOptions` 设计
+#[derive(Debug, Clone)]
+struct MoveOptions {
+    /// 目标已存在时是否直接覆盖
+    overwrite: bool,
+    /// 目标已存在时是否跳过（与 `overwrite` 冲突时以 `overwrite` 优先）
+    skip_exist: bool,
+    /// 只移动 `src` 的内容到 `dest`，不在 `dest` 下重新创建顶层目录
+    content_only: bool,
+    /// 限制 `WalkDir` 的递归深度，0 表示不限制
+    depth: u64,
+    /// 并发移动使用的线程数
+    num_threads: usize,
+    /// 跨设备拷贝时使用的缓冲区大小（字节）
+    buffer_size: usize,
+    /// 目标已存在时，仅当源文件的修改时间比目标新才移动，否则保留目标并删除源
+    update: bool,
+    /// 覆盖目标前的备份策略
+    backup: BackupMode,
+    /// 跨设备拷贝后，读回并比对源和目标，确认无误再删除源
+    verify: bool,
+    /// 覆盖前把已存在的目标移进系统回收站，而不是直接删除，提供一条撤销路径
+    trash_on_conflict: bool,
+}
+
+impl Default for MoveOptions {
+    fn default() -> Self {
+        Self {
+            overwrite: false,
+            skip_exist: true,
+            content_only: true,
+            depth: 0,
+            num_threads: 3,
+            buffer_size: 64 * 1024,
+            update: false,
+            backup: BackupMode::None,
+            verify: false,
+            trash_on_conflict: false,
+        }
+    }
+}
+
+/// 覆盖已存在目标前的备份策略，参考 coreutils `mv` 的 `--backup` 选项
+#[derive(Debug, Clone, Default, PartialEq)]
+enum BackupMode {
+    /// 不备份，直接覆盖（默认）
+    #[default]
+    None,
+    /// 备份为 `file~`，如已存在则覆盖上一次的备份
+    Simple,
+    /// 备份为 `file.~1~`、`file.~2~`……取第一个不存在的编号
+    Numbered,
 }
 
 /// 创建目录，支持 Windows 超长路径（>260 字符）
@@ -52,8 +123,8 @@ fn create_dir_with_long_path_support(path: &Path) -> Result<(), std::io::Error>
     fs::create_dir_all(path)
 }
 
-/// 移动目录，跳过已存在的文件
-fn move_directory_concurrent(src_dir: &Path, dest_dir: &Path) -> Result<()> {
+/// 移动目录，冲突处理策略由 `MoveOptions` 决定
+fn move_directory_concurrent(src_dir: &Path, dest_dir: &Path, options: &MoveOptions) -> Result<()> {
     if !src_dir.exists() {
         return Err(anyhow::anyhow!("Source directory does not exist"));
     }
@@ -61,25 +132,70 @@ fn move_directory_concurrent(src_dir: &Path, dest_dir: &Path) -> Result<()> {
         return Err(anyhow::anyhow!("Source is not a directory"));
     }
 
+    // content_only = false 时，在 dest_dir 下重新创建 src_dir 的顶层目录
+    let base_dir = if options.content_only {
+        dest_dir.to_path_buf()
+    } else {
+        let top_name = src_dir
+            .file_name()
+            .context("Failed to determine source directory name")?;
+        dest_dir.join(top_name)
+    };
+
     println!("Scanning files in {:?}...", src_dir);
     let mut tasks = Vec::new();
 
-    for entry in walkdir::WalkDir::new(src_dir).follow_links(false) {
+    let mut walker = walkdir::WalkDir::new(src_dir).follow_links(false);
+    if options.depth > 0 {
+        walker = walker.max_depth(options.depth as usize);
+    }
+
+    // (dev, inode) -> 该 inode 第一次出现时分配到的目标路径，用于识别并重建硬链接
+    #[cfg(unix)]
+    let mut inodes_seen: HashMap<(u64, u64), PathBuf> = HashMap::new();
+
+    for entry in walker {
         let entry = entry.context("Failed to read directory entry")?;
         let src_path = entry.path();
 
         let rel_path = src_path.strip_prefix(src_dir)
             .context("Failed to compute relative path")?;
-        let dst_path = dest_dir.join(rel_path);
+        let dst_path = base_dir.join(rel_path);
+
+        let file_type = entry.file_type();
+        let (size, kind) = if file_type.is_symlink() {
+            (0, EntryKind::Symlink)
+        } else if file_type.is_dir() {
+            (0, EntryKind::Dir)
+        } else {
+            let metadata = entry.metadata().context("Failed to read file metadata")?;
+
+            #[cfg(unix)]
+            {
+                let key = (metadata.dev(), metadata.ino());
+                if let Some(first_dst) = inodes_seen.get(&key) {
+                    (0, EntryKind::HardlinkOf(first_dst.clone()))
+                } else {
+                    inodes_seen.insert(key, dst_path.clone());
+                    (metadata.len(), EntryKind::File)
+                }
+            }
+            #[cfg(not(unix))]
+            {
+                (metadata.len(), EntryKind::File)
+            }
+        };
 
         tasks.push(FileMoveTask {
             src: src_path.to_path_buf(),
             dst: dst_path,
+            size,
+            kind,
         });
     }
 
     if tasks.is_empty() {
-        fs::create_dir_all(dest_dir)?;
+        fs::create_dir_all(&base_dir)?;
         println!("Source is empty, ensured destination directory exists.");
         return Ok(());
     }
@@ -87,22 +203,28 @@ fn move_directory_concurrent(src_dir: &Path, dest_dir: &Path) -> Result<()> {
     // 提前保存总数
     let total_count = tasks.len();
 
-    // 过滤：只处理目标不存在的项
-    let tasks_to_process: Vec<_> = tasks
-        .into_iter()
-        .filter(|task| {
-            if task.src.is_dir() {
-                !task.dst.exists()
-            } else if task.src.is_file() {
-                !task.dst.exists()
+    // 过滤：跳过按策略应跳过的项，既不覆盖也不跳过的冲突直接报错
+    let mut tasks_to_process = Vec::with_capacity(total_count);
+    let mut skipped_count = 0usize;
+    for task in tasks {
+        let is_conflict = path_present(&task.dst);
+        if is_conflict {
+            if options.overwrite || options.update {
+                tasks_to_process.push(task);
+            } else if options.skip_exist {
+                skipped_count += 1;
             } else {
-                true
+                return Err(anyhow::anyhow!(
+                    "Destination already exists: {:?}",
+                    task.dst
+                ));
             }
-        })
-        .collect();
+        } else {
+            tasks_to_process.push(task);
+        }
+    }
 
     let to_process_count = tasks_to_process.len();
-    let skipped_count = total_count - to_process_count;
 
     if to_process_count == 0 {
         println!("🎉 All {} files already exist at destination. Nothing to move.", total_count);
@@ -111,37 +233,36 @@ fn move_directory_concurrent(src_dir: &Path, dest_dir: &Path) -> Result<()> {
 
     println!("Processing {} items ({} skipped).", to_process_count, skipped_count);
 
-    // 进度条
-    let pb = ProgressBar::new(to_process_count as u64);
+    // 进度条：按字节数驱动，而不是按文件数，这样少量大文件也能看到平滑的进度
+    let total_bytes: u64 = tasks_to_process.iter().map(|task| task.size).sum();
+    let pb = ProgressBar::new(total_bytes);
     pb.set_style(
         ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
             .unwrap()
             .progress_chars("#>-"),
     );
     let pb = Mutex::new(pb);
 
-    // 设置线程数为 3（SSD → HDD 最佳）
+    // 设置并发线程数
     rayon::ThreadPoolBuilder::new()
-        .num_threads(3)
+        .num_threads(options.num_threads)
         .build_global()
         .expect("Failed to set Rayon thread pool");
 
-    // 并发处理
-    let results: Vec<Result<()>> = tasks_to_process
-        .par_iter()
-        .map(|task| {
-            let result = move_single_item(&task.src, &task.dst)
-                .with_context(|| format!("Failed to move {:?} -> {:?}", task.src, task.dst));
+    // 硬链接依赖它指向的原始文件先落地，因此分两批处理：先移动所有非硬链接条目，
+    // 等这批并发任务全部完成（屏障）后，原始文件必定已在目标位置，再处理硬链接条目
+    let (hardlink_tasks, originals_tasks): (Vec<_>, Vec<_>) = tasks_to_process
+        .into_iter()
+        .partition(|task| matches!(task.kind, EntryKind::HardlinkOf(_)));
 
-            if result.is_ok() {
-                let guard = pb.lock().unwrap();
-                guard.inc(1);
-            }
+    let move_task = |task: &FileMoveTask| {
+        move_single_item(&task.src, &task.dst, &task.kind, options, &pb)
+            .with_context(|| format!("Failed to move {:?} -> {:?}", task.src, task.dst))
+    };
 
-            result
-        })
-        .collect();
+    let mut results: Vec<Result<()>> = originals_tasks.par_iter().map(move_task).collect();
+    results.extend(hardlink_tasks.par_iter().map(move_task).collect::<Vec<_>>());
 
     // 收集错误（只返回第一个）
     for result in results {
@@ -153,63 +274,446 @@ fn move_directory_concurrent(src_dir: &Path, dest_dir: &Path) -> Result<()> {
 
     pb.lock().unwrap().finish_with_message("done");
 
-    // 尝试删除源目录（仅当为空）
-    if let Err(e) = fs::remove_dir(src_dir) {
-        if e.kind() != std::io::ErrorKind::NotFound && e.kind() != std::io::ErrorKind::DirectoryNotEmpty {
-            eprintln!("Warning: Could not remove source root dir '{}': {}", src_dir.display(), e);
+    // 自底向上清理源目录树中已清空的子目录
+    cleanup_source_tree(src_dir)?;
+
+    Ok(())
+}
+
+/// 自底向上遍历源目录树并逐个删除空目录；`NotFound`（目录已被并发任务或之前的运行删除）
+/// 和 `DirectoryNotEmpty`（被跳过的文件仍留在里面）都不算错误，只有权限等真正的错误才会返回
+fn cleanup_source_tree(src_dir: &Path) -> Result<()> {
+    let dirs: Vec<PathBuf> = walkdir::WalkDir::new(src_dir)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+        .map(|entry| entry.path().to_path_buf())
+        .collect();
+
+    for dir in dirs {
+        match fs::remove_dir(&dir) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) if e.kind() == std::io::ErrorKind::DirectoryNotEmpty => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to remove source directory {:?}", dir));
+            }
         }
     }
 
     Ok(())
 }
 
-/// 移动单个文件或目录，支持长路径
-fn move_single_item(src: &Path, dst: &Path) -> Result<()> {
-    if dst.exists() {
-        return Ok(()); // ✅ 所有情况都先检查
-    }
+/// 判断路径是否存在（symlink_metadata 能识别断链的符号链接，而 `Path::exists` 不能）
+fn path_present(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
 
-    if src.is_dir() {
-        let _ = create_dir_with_long_path_support(dst); // 忽略 AlreadyExists
-        Ok(())
-    } else if src.is_file() {
+/// 移动单个条目，支持长路径；冲突处理策略由 `MoveOptions` 决定，具体重建方式由 `EntryKind` 决定
+///
+/// 目录条目只做"合并"：它只负责把 `dst` 目录本身建出来，已存在就原样保留，绝不对它执行
+/// `overwrite`/`update` 的清理或删除逻辑。任务是按 `par_iter` 并发处理的，目录和它内部的
+/// 文件是各自独立的任务，谁先落地没有保证；对目录调用 `remove_dir_all`（无论是清空 dst
+/// 还是当作"源已过时"去删 src）都可能打掉并发落地的兄弟文件。真正的冲突处理只对文件叶子
+/// 节点生效。
+fn move_single_item(src: &Path, dst: &Path, kind: &EntryKind, options: &MoveOptions, pb: &Mutex<ProgressBar>) -> Result<()> {
+    if matches!(kind, EntryKind::Dir) {
         if let Some(parent) = dst.parent() {
             let _ = create_dir_with_long_path_support(parent);
         }
+        let _ = create_dir_with_long_path_support(dst); // 已存在就合并，忽略 AlreadyExists
+        return Ok(());
+    }
 
-        if dst.exists() {
-            return Ok(()); // 再次确认
-        }
-
-        // 尝试移动
-        if fs::rename(src, dst).is_err() {
-            // 如果 rename 失败（比如跨盘符），尝试 copy + remove
-            // 但 copy 前再检查一次
-            if !dst.exists() {
-                match fs::copy(src, dst) {
-                    Ok(_) => {}
-                    Err(e) => {
-                        if e.kind() == std::io::ErrorKind::AlreadyExists {
-                            return Ok(()); // 安全跳过
-                        } else {
-                            return Err(e).context("Copy failed");
-                        }
-                    }
-                }
+    if path_present(dst) {
+        if options.update {
+            if is_src_newer(src, dst)? {
+                clear_destination(dst, options)?;
+            } else {
+                // 目标已是最新，保留目标，丢弃源
+                remove_path(src)?;
+                return Ok(());
             }
-            let _ = fs::remove_file(src); // 尝试删除源，失败也无所谓
+        } else if options.overwrite {
+            clear_destination(dst, options)?;
+        } else if options.skip_exist {
+            return Ok(()); // 按策略跳过
+        } else {
+            return Err(anyhow::anyhow!("Destination already exists: {:?}", dst));
         }
+    }
 
-        Ok(())
+    if let Some(parent) = dst.parent() {
+        let _ = create_dir_with_long_path_support(parent);
+    }
+
+    match kind {
+        EntryKind::Dir => unreachable!("directory entries return early above"),
+        EntryKind::Symlink => move_symlink(src, dst),
+        EntryKind::HardlinkOf(first_dst) => move_hardlink(src, dst, first_dst, options, pb),
+        EntryKind::File => move_file(src, dst, options, pb),
+    }
+}
+
+/// 重新创建符号链接本身（而不是拷贝其指向的内容），然后移除源链接
+fn move_symlink(src: &Path, dst: &Path) -> Result<()> {
+    if fs::rename(src, dst).is_ok() {
+        return Ok(());
+    }
+
+    let target = fs::read_link(src).context("Failed to read symlink target")?;
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&target, dst).context("Failed to recreate symlink")?;
+    }
+    #[cfg(windows)]
+    {
+        if target.is_dir() {
+            std::os::windows::fs::symlink_dir(&target, dst).context("Failed to recreate directory symlink")?;
+        } else {
+            std::os::windows::fs::symlink_file(&target, dst).context("Failed to recreate file symlink")?;
+        }
+    }
+
+    let _ = fs::remove_file(src);
+    Ok(())
+}
+
+/// 与某个已经移动的文件共享 inode：优先用硬链接复用目标文件（硬链接条目在原始文件
+/// 之后单独一批处理，保证 `first_dst` 此时已经落地），如果目标所在设备不支持硬链接
+/// （例如跨文件系统）才退化为普通拷贝；退化时这部分字节原先未计入总量，需要用
+/// `inc_length` 把它补进进度条总长度，避免进度超过 100%
+fn move_hardlink(src: &Path, dst: &Path, first_dst: &Path, options: &MoveOptions, pb: &Mutex<ProgressBar>) -> Result<()> {
+    if first_dst != dst && fs::hard_link(first_dst, dst).is_ok() {
+        let _ = fs::remove_file(src);
+        return Ok(());
+    }
+
+    let file_size = fs::metadata(src).map(|m| m.len()).unwrap_or(0);
+    pb.lock().unwrap().inc_length(file_size);
+    move_file(src, dst, options, pb)
+}
+
+/// 移动普通文件：同盘用 `rename`，跨盘符退化为缓冲区流式拷贝，可选做完整性校验
+fn move_file(src: &Path, dst: &Path, options: &MoveOptions, pb: &Mutex<ProgressBar>) -> Result<()> {
+    let file_size = fs::metadata(src).map(|m| m.len()).unwrap_or(0);
+
+    // 尝试移动
+    if fs::rename(src, dst).is_err() {
+        // 如果 rename 失败（比如跨盘符），按缓冲区分块拷贝，逐块推进进度条
+        stream_copy(src, dst, options.buffer_size, pb)?;
+
+        if options.verify && !files_match(src, dst, options.buffer_size)? {
+            remove_path(dst)
+                .context("Failed to remove corrupted destination after verification failure")?;
+            return Err(anyhow::anyhow!(
+                "Verification failed: {:?} and {:?} do not match, source preserved",
+                src,
+                dst
+            ));
+        }
+
+        let _ = fs::remove_file(src); // 尝试删除源，失败也无所谓
     } else {
-        Ok(())
+        pb.lock().unwrap().inc(file_size);
+    }
+
+    Ok(())
+}
+
+/// 判断 `src` 的修改时间是否比 `dst` 新
+fn is_src_newer(src: &Path, dst: &Path) -> Result<bool> {
+    let src_modified = fs::metadata(src)
+        .and_then(|m| m.modified())
+        .context("Failed to read source modification time")?;
+    let dst_modified = fs::metadata(dst)
+        .and_then(|m| m.modified())
+        .context("Failed to read destination modification time")?;
+    Ok(src_modified > dst_modified)
+}
+
+/// 删除文件或目录
+fn remove_path(path: &Path) -> Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path).context("Failed to remove directory")
+    } else {
+        fs::remove_file(path).context("Failed to remove file")
+    }
+}
+
+/// 清理已存在的目标：`trash_on_conflict` 优先送入系统回收站，否则按 `BackupMode` 处理
+/// （`None` 直接删除，`Simple`/`Numbered` 先重命名为备份文件）
+fn clear_destination(dst: &Path, options: &MoveOptions) -> Result<()> {
+    if options.trash_on_conflict {
+        return move_to_trash(dst);
+    }
+
+    match &options.backup {
+        BackupMode::None => remove_path(dst),
+        BackupMode::Simple => {
+            let backup = simple_backup_path(dst);
+            fs::rename(dst, &backup).context("Failed to create simple backup")
+        }
+        BackupMode::Numbered => {
+            let backup = numbered_backup_path(dst)?;
+            fs::rename(dst, &backup).context("Failed to create numbered backup")
+        }
+    }
+}
+
+/// 构造形如 `file~` 的备份路径
+fn simple_backup_path(dst: &Path) -> PathBuf {
+    let mut name = dst.file_name().unwrap_or_default().to_os_string();
+    name.push("~");
+    dst.with_file_name(name)
+}
+
+/// 扫描第一个空闲编号，构造形如 `file.~1~`、`file.~2~` 的备份路径
+fn numbered_backup_path(dst: &Path) -> Result<PathBuf> {
+    let mut index = 1u64;
+    loop {
+        let mut name = dst.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".~{}~", index));
+        let candidate = dst.with_file_name(name);
+        if !candidate.exists() {
+            return Ok(candidate);
+        }
+        index += 1;
+    }
+}
+
+/// `buffer_size` 为 0 时 `read` 永远返回 0 字节，拷贝循环会在写入任何内容之前就
+/// "成功" 结束——目标文件建出来了但是空的，随后源文件还会被当作已移动而删除，
+/// 造成静默数据丢失。所以这里兜底夹到最小值 1，而不是信任调用方已经校验过。
+const MIN_BUFFER_SIZE: usize = 1;
+
+/// 以固定大小缓冲区分块拷贝 `src` 到 `dst`，每写完一块就推进进度条对应的字节数
+fn stream_copy(src: &Path, dst: &Path, buffer_size: usize, pb: &Mutex<ProgressBar>) -> Result<()> {
+    let mut reader = fs::File::open(src).context("Failed to open source file")?;
+    let mut writer = fs::File::create(dst).context("Failed to create destination file")?;
+    let mut buffer = vec![0u8; buffer_size.max(MIN_BUFFER_SIZE)];
+
+    loop {
+        let n = reader.read(&mut buffer).context("Failed to read from source file")?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buffer[..n]).context("Failed to write to destination file")?;
+        pb.lock().unwrap().inc(n as u64);
+    }
+
+    writer.flush().context("Failed to flush destination file")?;
+    Ok(())
+}
+
+/// 先比较文件大小，再逐块读回比对内容，确认 `src` 和 `dst` 字节完全一致
+fn files_match(src: &Path, dst: &Path, buffer_size: usize) -> Result<bool> {
+    let src_len = fs::metadata(src).context("Failed to read source metadata")?.len();
+    let dst_len = fs::metadata(dst).context("Failed to read destination metadata")?.len();
+    if src_len != dst_len {
+        return Ok(false);
+    }
+
+    let mut src_reader = fs::File::open(src).context("Failed to reopen source file for verification")?;
+    let mut dst_reader = fs::File::open(dst).context("Failed to reopen destination file for verification")?;
+    let buffer_size = buffer_size.max(MIN_BUFFER_SIZE);
+    let mut src_buf = vec![0u8; buffer_size];
+    let mut dst_buf = vec![0u8; buffer_size];
+
+    loop {
+        let src_n = src_reader.read(&mut src_buf).context("Failed to read source during verification")?;
+        let dst_n = dst_reader.read(&mut dst_buf).context("Failed to read destination during verification")?;
+        if src_n != dst_n {
+            return Ok(false);
+        }
+        if src_n == 0 {
+            return Ok(true);
+        }
+        if src_buf[..src_n] != dst_buf[..dst_n] {
+            return Ok(false);
+        }
+    }
+}
+
+/// 按 freedesktop.org Trash spec 的要求对 `.trashinfo` 的 `Path=` 值做 RFC 2396 风格的
+/// URL 编码：保留非保留字符和路径分隔符 `/`，其余字节一律转成大写 `%XX`。不编码的话，
+/// 路径里的空格、`#`、非 ASCII 字符会让记录无法被真正的回收站实现解析，"撤销路径" 也就
+/// 形同虚设。
+#[cfg(target_os = "linux")]
+fn percent_encode_path(path: &Path) -> String {
+    fn is_unreserved(byte: u8) -> bool {
+        byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~')
+    }
+
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_encoded_bytes() {
+        if byte == b'/' || is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// 将 `path` 移入系统回收站，而不是直接删除，为覆盖操作提供撤销路径
+#[cfg(target_os = "linux")]
+fn move_to_trash(path: &Path) -> Result<()> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let home = std::env::var_os("HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from("."));
+            home.join(".local/share")
+        });
+
+    let files_dir = data_home.join("Trash").join("files");
+    let info_dir = data_home.join("Trash").join("info");
+    fs::create_dir_all(&files_dir).context("Failed to create Trash/files directory")?;
+    fs::create_dir_all(&info_dir).context("Failed to create Trash/info directory")?;
+
+    let file_name = path.file_name().context("Trashed path has no file name")?;
+
+    // 处理同名冲突：追加计数器直到找到一个 files/ 和 info/ 下都空闲的名字
+    let mut counter = 0u32;
+    let (trashed_path, info_path) = loop {
+        let candidate_name = if counter == 0 {
+            file_name.to_os_string()
+        } else {
+            let mut name = file_name.to_os_string();
+            name.push(format!("_{}", counter));
+            name
+        };
+        let trashed_path = files_dir.join(&candidate_name);
+        let info_path = info_dir.join(format!("{}.trashinfo", candidate_name.to_string_lossy()));
+        if !path_present(&trashed_path) && !info_path.exists() {
+            break (trashed_path, info_path);
+        }
+        counter += 1;
+    };
+
+    let original_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let info_contents = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode_path(&original_path),
+        chrono::Local::now().to_rfc3339(),
+    );
+    fs::write(&info_path, info_contents).context("Failed to write .trashinfo record")?;
+
+    if fs::rename(path, &trashed_path).is_err() {
+        // Trash 目录和源不在同一个文件系统，退化为拷贝 + 删除
+        if path.is_dir() {
+            copy_dir_recursive(path, &trashed_path)?;
+            fs::remove_dir_all(path).context("Failed to remove original after copying to trash")?;
+        } else {
+            fs::copy(path, &trashed_path).context("Failed to copy file into trash")?;
+            fs::remove_file(path).context("Failed to remove original after copying to trash")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Windows/macOS 直接调用系统回收站 API
+#[cfg(any(windows, target_os = "macos"))]
+fn move_to_trash(path: &Path) -> Result<()> {
+    trash::delete(path).context("Failed to move path to system trash")
+}
+
+/// 递归拷贝目录，供回收站跨文件系统兜底使用
+#[cfg(target_os = "linux")]
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src).context("Failed to read directory for trash copy")? {
+        let entry = entry.context("Failed to read directory entry for trash copy")?;
+        let entry_dst = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive(&entry.path(), &entry_dst)?;
+        } else {
+            fs::copy(entry.path(), &entry_dst).context("Failed to copy file for trash")?;
+        }
+    }
+    Ok(())
+}
+
+/// 解析 `--backup <none|simple|numbered>` 的取值
+fn parse_backup_mode(value: &str) -> Result<BackupMode> {
+    match value {
+        "none" => Ok(BackupMode::None),
+        "simple" => Ok(BackupMode::Simple),
+        "numbered" => Ok(BackupMode::Numbered),
+        other => Err(anyhow::anyhow!(
+            "Invalid --backup value '{}', expected none|simple|numbered",
+            other
+        )),
     }
 }
 
+/// 解析命令行参数：非 `--` 开头的位置参数依次是 src、dest 路径，其余按 `--flag [value]`
+/// 解析为 `MoveOptions`（未出现的选项沿用 `MoveOptions::default()`）。位置参数和带值的
+/// flag 必须在同一趟扫描里处理，否则 flag 的值会被误当成位置参数吃掉。
+fn parse_args(args: &[String]) -> Result<(Vec<String>, MoveOptions)> {
+    let mut options = MoveOptions::default();
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--overwrite" => options.overwrite = true,
+            "--no-skip-exist" => options.skip_exist = false,
+            "--recreate-top" => options.content_only = false,
+            "--update" => options.update = true,
+            "--verify" => options.verify = true,
+            "--trash" => options.trash_on_conflict = true,
+            "--depth" => {
+                let value = iter.next().context("--depth requires a value")?;
+                options.depth = value.parse().context("--depth expects a non-negative integer")?;
+            }
+            "--threads" => {
+                let value = iter.next().context("--threads requires a value")?;
+                let parsed: usize = value.parse().context("--threads expects a positive integer")?;
+                if parsed == 0 {
+                    return Err(anyhow::anyhow!("--threads must be greater than 0"));
+                }
+                options.num_threads = parsed;
+            }
+            "--buffer-size" => {
+                let value = iter.next().context("--buffer-size requires a value")?;
+                let parsed: usize = value.parse().context("--buffer-size expects a positive integer")?;
+                if parsed == 0 {
+                    return Err(anyhow::anyhow!("--buffer-size must be greater than 0"));
+                }
+                options.buffer_size = parsed;
+            }
+            "--backup" => {
+                let value = iter.next().context("--backup requires a value")?;
+                options.backup = parse_backup_mode(value)?;
+            }
+            other if other.starts_with("--") => {
+                return Err(anyhow::anyhow!("Unknown option '{}'", other));
+            }
+            other => positional.push(other.to_string()),
+        }
+    }
+
+    Ok((positional, options))
+}
+
 fn main() -> Result<()> {
-    // ⚠️ 修改为你自己的路径
-    let src = Path::new(r"D:\dev\code");   // 例如：SSD 上的文件夹
-    let dest = Path::new(r"E:\dev");  // 例如：HDD 上的目标
+    let raw_args: Vec<String> = std::env::args().skip(1).collect();
+    let (positional, options) = parse_args(&raw_args)?;
+
+    // 前两个位置参数是 src/dest 路径；没传的那个回退到下面对应的默认值，方便改完直接跑
+    let default_src = r"D:\dev\code".to_string(); // ⚠️ 没传路径时，改成你自己的源路径
+    let default_dest = r"E:\dev".to_string();     // ⚠️ 没传路径时，改成你自己的目标路径
+    let mut positional = positional.into_iter();
+    let src = positional.next().unwrap_or(default_src);
+    let dest = positional.next().unwrap_or(default_dest);
+    let src = Path::new(&src);
+    let dest = Path::new(&dest);
 
     if !src.exists() {
         eprintln!("Source path does not exist: {:?}", src);
@@ -219,7 +723,7 @@ fn main() -> Result<()> {
     let start = Instant::now();
     println!("🚀 Starting move from {:?} to {:?}", src, dest);
 
-    match move_directory_concurrent(src, dest) {
+    match move_directory_concurrent(src, dest, &options) {
         Ok(()) => {
             println!("✅ Success! Total time: {:?}", start.elapsed());
         }
@@ -231,3 +735,209 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unique_test_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("quickshift_test_{}_{}", name, std::process::id()))
+    }
+
+    /// 回归测试：目标目录已存在且 `update` 为 true 时，不能因为顶层目录的冲突解决
+    /// 就对整个源子树 `remove_dir_all`——未被处理到的同级文件必须原样留在源目录里。
+    #[test]
+    fn update_mode_never_wholesale_deletes_source_directory() {
+        let root = unique_test_dir("update_dir");
+        let src_dir = root.join("src");
+        let dst_dir = root.join("dst");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&src_dir).unwrap();
+        fs::create_dir_all(&dst_dir).unwrap();
+
+        let untouched = src_dir.join("untouched.txt");
+        fs::write(&untouched, b"still here").unwrap();
+
+        let options = MoveOptions {
+            update: true,
+            ..MoveOptions::default()
+        };
+        let pb = Mutex::new(ProgressBar::hidden());
+
+        move_single_item(&src_dir, &dst_dir, &EntryKind::Dir, &options, &pb).unwrap();
+
+        assert!(dst_dir.is_dir(), "destination directory must still exist");
+        assert!(
+            untouched.exists(),
+            "update on a directory entry must not remove_dir_all the source subtree"
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn trashinfo_path_is_percent_encoded() {
+        let path = Path::new("/tmp/a b#c/文件.txt");
+        let encoded = percent_encode_path(path);
+        assert_eq!(encoded, "/tmp/a%20b%23c/%E6%96%87%E4%BB%B6.txt");
+    }
+
+    /// 代理验证"跨设备拷贝 + 校验"：`stream_copy` 走的就是跨盘符退化路径，
+    /// `files_match` 是 `--verify` 用来确认拷贝无误的比对函数。这里篡改目标内容，
+    /// 确认比对能发现损坏而不是误判通过。
+    #[test]
+    fn files_match_detects_corruption_after_stream_copy() {
+        let root = unique_test_dir("stream_copy");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let src = root.join("src.bin");
+        let dst = root.join("dst.bin");
+        fs::write(&src, b"the quick brown fox jumps over the lazy dog").unwrap();
+
+        let pb = Mutex::new(ProgressBar::hidden());
+        stream_copy(&src, &dst, 8, &pb).unwrap();
+        assert!(files_match(&src, &dst, 8).unwrap(), "freshly copied files must match");
+
+        let mut corrupted = fs::OpenOptions::new().write(true).open(&dst).unwrap();
+        corrupted.write_all(b"X").unwrap();
+        drop(corrupted);
+        assert!(
+            !files_match(&src, &dst, 8).unwrap(),
+            "files_match must detect byte-level corruption"
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn simple_backup_path_appends_tilde() {
+        let dst = Path::new("/tmp/quickshift/notes.txt");
+        assert_eq!(simple_backup_path(dst), Path::new("/tmp/quickshift/notes.txt~"));
+    }
+
+    #[test]
+    fn numbered_backup_path_picks_first_free_index() {
+        let root = unique_test_dir("numbered_backup");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let dst = root.join("notes.txt");
+        fs::write(&dst, b"current").unwrap();
+        fs::write(root.join("notes.txt.~1~"), b"old backup 1").unwrap();
+
+        let backup = numbered_backup_path(&dst).unwrap();
+        assert_eq!(backup, root.join("notes.txt.~2~"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    fn file_conflict_case(options: MoveOptions) -> (PathBuf, PathBuf, Result<()>) {
+        let root = unique_test_dir(&format!(
+            "conflict_{}_{}_{}",
+            options.overwrite, options.skip_exist, options.update
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let src = root.join("src.txt");
+        let dst = root.join("dst.txt");
+        fs::write(&src, b"new content").unwrap();
+        fs::write(&dst, b"old content").unwrap();
+
+        // 两次 write 之间的系统时钟分辨率不足以保证 mtime 有先后之分，
+        // update 模式的测试用例依赖 src 确实比 dst 新，这里显式拉开两者的 mtime
+        let now = std::time::SystemTime::now();
+        fs::File::open(&dst).unwrap().set_modified(now - std::time::Duration::from_secs(60)).unwrap();
+        fs::File::open(&src).unwrap().set_modified(now).unwrap();
+
+        let pb = Mutex::new(ProgressBar::hidden());
+        let result = move_single_item(&src, &dst, &EntryKind::File, &options, &pb);
+        (src, dst, result)
+    }
+
+    /// skip/overwrite/update 在文件叶子节点上的冲突处理矩阵：三种策略互斥生效，
+    /// 行为必须和各自的文档注释一致。
+    #[test]
+    fn file_conflict_matrix() {
+        // skip_exist：保留目标，源原样留下（不会被当成"已处理"而删除）
+        let (src, dst, result) = file_conflict_case(MoveOptions {
+            overwrite: false,
+            skip_exist: true,
+            update: false,
+            ..MoveOptions::default()
+        });
+        result.unwrap();
+        assert_eq!(fs::read(&dst).unwrap(), b"old content");
+        assert!(src.exists(), "skip must leave the source file in place");
+        let _ = fs::remove_dir_all(src.parent().unwrap());
+
+        // overwrite：目标被替换为源的内容
+        let (_src, dst, result) = file_conflict_case(MoveOptions {
+            overwrite: true,
+            skip_exist: false,
+            update: false,
+            ..MoveOptions::default()
+        });
+        result.unwrap();
+        assert_eq!(fs::read(&dst).unwrap(), b"new content");
+        let _ = fs::remove_dir_all(dst.parent().unwrap());
+
+        // update：源比目标新，替换目标
+        let (_src, dst, result) = file_conflict_case(MoveOptions {
+            overwrite: false,
+            skip_exist: false,
+            update: true,
+            ..MoveOptions::default()
+        });
+        result.unwrap();
+        assert_eq!(fs::read(&dst).unwrap(), b"new content");
+        let _ = fs::remove_dir_all(dst.parent().unwrap());
+
+        // 既不 overwrite 也不 skip_exist 也不 update：按策略报错，两边都不动
+        let (src, dst, result) = file_conflict_case(MoveOptions {
+            overwrite: false,
+            skip_exist: false,
+            update: false,
+            ..MoveOptions::default()
+        });
+        assert!(result.is_err(), "no conflict strategy selected must error out");
+        assert_eq!(fs::read(&dst).unwrap(), b"old content");
+        assert!(src.exists());
+        let _ = fs::remove_dir_all(src.parent().unwrap());
+    }
+
+    /// 硬链接去重：源目录下两个互为硬链接的文件，移动后目标侧必须仍然共享同一个 inode，
+    /// 而不是各自拷贝成独立文件。只有这一个测试会调用 `move_directory_concurrent`
+    /// （它内部通过 `build_global` 构建一次性的全局 Rayon 线程池，一个进程内只能成功一次）。
+    #[cfg(unix)]
+    #[test]
+    fn move_directory_concurrent_dedups_hardlinks() {
+        let root = unique_test_dir("hardlink_dedup");
+        let src_dir = root.join("src");
+        let dst_dir = root.join("dst");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&src_dir).unwrap();
+
+        let first = src_dir.join("first.txt");
+        let second = src_dir.join("second.txt");
+        fs::write(&first, b"shared content").unwrap();
+        fs::hard_link(&first, &second).unwrap();
+        assert_eq!(
+            fs::metadata(&first).unwrap().ino(),
+            fs::metadata(&second).unwrap().ino(),
+            "test setup must start from two hardlinked files"
+        );
+
+        let options = MoveOptions::default();
+        move_directory_concurrent(&src_dir, &dst_dir, &options).unwrap();
+
+        let moved_first = dst_dir.join("first.txt");
+        let moved_second = dst_dir.join("second.txt");
+        assert_eq!(
+            fs::metadata(&moved_first).unwrap().ino(),
+            fs::metadata(&moved_second).unwrap().ino(),
+            "hardlinked files must still share an inode after the concurrent move"
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}